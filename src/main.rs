@@ -1,10 +1,14 @@
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use log::{debug, error, info};
 use simple_logger::SimpleLogger;
+use std::ffi::OsString;
 use std::fs::{canonicalize, File};
 use std::io::{BufRead, BufReader, Write};
 use std::path::{Path, PathBuf};
 use std::process::{exit, Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
 
 #[derive(Parser)]
 #[command(version, author, about, dont_collapse_args_in_usage = true)]
@@ -13,6 +17,46 @@ struct Args {
     #[arg(long)]
     tempdir: Option<PathBuf>,
 
+    /// Backend used to join the kept pieces back together
+    #[arg(long, value_enum, default_value_t = ConcatMethod::Ffmpeg)]
+    concat_method: ConcatMethod,
+
+    /// Number of pieces to extract in parallel (defaults to the number of
+    /// available CPUs)
+    #[arg(long)]
+    jobs: Option<usize>,
+
+    /// Noise tolerance, as an amplitude ratio (e.g. 0.03), below which audio
+    /// is considered silent, forwarded to ffmpeg's `silencedetect` filter
+    #[arg(long, env = "JUMPCUTTER_NOISE", default_value_t = 0.03)]
+    noise: f32,
+
+    /// Minimum duration (in seconds) of silence for it to be detected
+    #[arg(long, env = "JUMPCUTTER_MIN_SILENCE", default_value_t = 0.1)]
+    min_silence: f32,
+
+    /// Seconds of padding kept on each side of a detected silence, to avoid
+    /// clipping consonants at the cut
+    #[arg(long, env = "JUMPCUTTER_MARGIN", default_value_t = 0.0)]
+    margin: f32,
+
+    /// Cross-fade duration (in seconds) applied between consecutive kept
+    /// segments instead of a hard cut; overrides `--concat-method`
+    #[arg(long)]
+    transition: Option<f32>,
+
+    /// What to do with detected silent regions: "remove" drops them (the
+    /// default); any other value is a speed factor (e.g. "4") applied to
+    /// keep them, time-compressed, instead. Sped-up pieces pass through
+    /// `setpts`/`atempo` filters while being extracted; either
+    /// `--concat-method` then joins them like any other piece
+    #[arg(long, default_value = "remove")]
+    silent_speed: SilentMode,
+
+    /// Playback-speed factor applied to sounded (kept) segments
+    #[arg(long, value_parser = parse_speed_factor)]
+    sounded_speed: Option<f32>,
+
     /// Input file path
     input_file: PathBuf,
 
@@ -20,6 +64,78 @@ struct Args {
     output_file: PathBuf,
 }
 
+/// A single, independent "extract this piece" job, as parsed from the
+/// `silencedetect` output.
+struct Job {
+    silence_end: f32,
+    duration: f32,
+    piece: PathBuf,
+    /// Playback-speed factor to apply while extracting this piece, if any.
+    speed: Option<f32>,
+}
+
+/// What to do with detected silent regions.
+#[derive(Clone)]
+enum SilentMode {
+    /// Drop silent regions entirely (the original behavior).
+    Remove,
+    /// Keep silent regions, time-compressed by this factor.
+    Speed(f32),
+}
+
+impl std::str::FromStr for SilentMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("remove") {
+            Ok(SilentMode::Remove)
+        } else {
+            parse_speed_factor(s)
+                .map(SilentMode::Speed)
+                .map_err(|err| format!("{err} (or \"remove\")"))
+        }
+    }
+}
+
+/// Parses a playback-speed factor, rejecting non-positive values: zero or
+/// negative factors make the `atempo_chain` normalization loop diverge.
+fn parse_speed_factor(s: &str) -> Result<f32, String> {
+    let factor: f32 = s
+        .parse()
+        .map_err(|_| format!("invalid speed factor {s:?}: expected a number"))?;
+    if factor > 0.0 {
+        Ok(factor)
+    } else {
+        Err(format!(
+            "invalid speed factor {s:?}: must be a positive number"
+        ))
+    }
+}
+
+/// Facts about the input file gathered by [`probe`], ahead of running
+/// `silencedetect` on it.
+struct ProbeInfo {
+    /// Total duration of the file, in seconds.
+    duration: f32,
+    /// Whether the file has at least one audio stream.
+    has_audio: bool,
+}
+
+/// Backend used by [`concatenate`] to join the kept pieces back together.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum ConcatMethod {
+    /// `ffmpeg`'s concat demuxer with `-c copy`.
+    ///
+    /// Fast, but pieces cut at non-keyframe timestamps can produce gaps,
+    /// desync, or an unplayable join.
+    Ffmpeg,
+    /// `mkvmerge -o <out> <first> + <second> + ...`.
+    ///
+    /// Slower, but handles appended segments with differing timestamps far
+    /// more robustly than `-c copy`.
+    Mkvmerge,
+}
+
 fn main() {
     SimpleLogger::new().init().unwrap_or_default();
     let args = Args::parse();
@@ -55,11 +171,27 @@ fn main() {
         exit(err.raw_os_error().unwrap_or(1))
     });
 
+    info!("Probe input file");
+    let probe_info = probe(&input_file).unwrap_or_else(|err| {
+        error!("Failed to probe {:?}: {}", input_file, err);
+        exit(1)
+    });
+    if !probe_info.has_audio {
+        error!(
+            "{:?} has no audio stream to run silencedetect on",
+            input_file
+        );
+        exit(1)
+    }
+
     info!("Detect silences");
     let mut ffmpeg = Command::new("ffmpeg")
         .arg("-i")
         .arg(&input_file)
-        .args(["-af", "silencedetect=n=0.03:d=0.1"])
+        .args([
+            "-af",
+            &format!("silencedetect=n={}:d={}", args.noise, args.min_silence),
+        ])
         .args(["-f", "null"])
         .arg("-")
         .stderr(Stdio::piped())
@@ -68,63 +200,382 @@ fn main() {
             error!("Failed to spawn ffmpeg: {}", err);
             exit(err.raw_os_error().unwrap_or(1))
         });
+    let mut jobs = Vec::new();
     if let Some(output) = ffmpeg.stderr.take() {
         let output = BufReader::new(output);
         let mut silence_end = 0.0;
+        let mut piece_end = 0.0; // End of the last emitted piece, so margins never overlap
+        let mut pending_silence_start = None;
         for (uniq, line) in output.lines().map_while(Result::ok).enumerate() {
             eprintln!("{}", &line);
             if let Some(pos) = line.find("silence_start: ") {
                 if let Some(silence_start) = line[pos..].split_whitespace().nth(1) {
                     if let Ok(silence_start) = silence_start.parse::<f32>() {
                         if (silence_start - silence_end).abs() > 0.01 {
-                            debug!("keep {}-{}", silence_end, silence_start);
+                            let start = (silence_end - args.margin).max(0.0).max(piece_end);
+                            let end = silence_start + args.margin;
+                            debug!("keep {}-{}", start, end);
                             let mut piece = tempdir.path().to_owned();
                             piece.push(format!("piece-{uniq:08x}.mkv"));
-                            writeln!(concat_script, "file {}", piece.to_string_lossy())
-                                .expect("Failed to write");
-                            slice(silence_end, silence_start - silence_end, &input_file, piece);
+                            jobs.push(Job {
+                                silence_end: start,
+                                duration: end - start,
+                                piece,
+                                speed: args.sounded_speed,
+                            });
+                            piece_end = end;
                         }
+                        pending_silence_start = Some(silence_start);
                     }
                 }
             } else if let Some(pos) = line.find("silence_end: ") {
                 if let Some(end) = line[pos..].split_whitespace().nth(1) {
-                    if let Ok(end) = end.parse() {
+                    if let Ok(end) = end.parse::<f32>() {
+                        if let (SilentMode::Speed(factor), Some(start)) =
+                            (&args.silent_speed, pending_silence_start.take())
+                        {
+                            let start = start.max(piece_end);
+                            let duration = (end - start).max(0.0);
+                            if duration > 0.01 {
+                                debug!("keep {}-{} at {}x (silent)", start, end, factor);
+                                let mut piece = tempdir.path().to_owned();
+                                piece.push(format!("piece-{uniq:08x}-silent.mkv"));
+                                jobs.push(Job {
+                                    silence_end: start,
+                                    duration,
+                                    piece,
+                                    speed: Some(*factor),
+                                });
+                                piece_end = end;
+                            }
+                        }
                         silence_end = end;
                     }
                 }
             }
         }
+
+        if (probe_info.duration - silence_end).abs() > 0.01
+            && !(matches!(args.silent_speed, SilentMode::Remove) && pending_silence_start.is_some())
+        {
+            let start = (silence_end - args.margin).max(0.0).max(piece_end);
+            let end = probe_info.duration;
+            // If the file ends while still inside an unclosed silence (no
+            // trailing `silence_end:` line was ever seen), this trailing
+            // span is silence, not sound: it is dropped in remove mode
+            // (above) and sped up here otherwise.
+            let speed = match (&args.silent_speed, pending_silence_start) {
+                (SilentMode::Speed(factor), Some(_)) => Some(*factor),
+                _ => args.sounded_speed,
+            };
+            debug!("keep {}-{} (trailing)", start, end);
+            let mut piece = tempdir.path().to_owned();
+            piece.push("piece-trailing.mkv");
+            jobs.push(Job {
+                silence_end: start,
+                duration: end - start,
+                piece,
+                speed,
+            });
+        }
     }
 
+    info!("Write concat script");
+    for job in &jobs {
+        writeln!(concat_script, "file {}", job.piece.to_string_lossy()).expect("Failed to write");
+    }
     drop(concat_script); // Flush and close the script
 
+    info!("Extract pieces");
+    let workers = args
+        .jobs
+        .or_else(|| thread::available_parallelism().ok().map(|n| n.get()))
+        .unwrap_or(1);
+    let errors = run_pooled(&jobs, workers, |job| {
+        slice(
+            job.silence_end,
+            job.duration,
+            &input_file,
+            &job.piece,
+            job.speed,
+        )
+    });
+    if !errors.is_empty() {
+        for err in &errors {
+            error!("{}", err);
+        }
+        error!("Failed to extract {} piece(s)", errors.len());
+        exit(1)
+    }
+
+    let piece_paths: Vec<_> = jobs.into_iter().map(|job| job.piece).collect();
+
     info!("Concatenate pieces");
-    concatenate(concat_script_path, args.output_file);
+    match args.transition {
+        Some(transition) => concatenate_xfade(&piece_paths, transition, args.output_file)
+            .unwrap_or_else(|err| {
+                error!("Failed to concatenate pieces with transitions: {}", err);
+                exit(1)
+            }),
+        None => concatenate(
+            args.concat_method,
+            concat_script_path,
+            &piece_paths,
+            args.output_file,
+        ),
+    }
 }
 
-fn slice<I, O>(timestamp: f32, duration: f32, input: I, output: O)
+/// Runs `ffprobe` against `input` to obtain its total duration, in seconds.
+fn ffprobe_duration<I>(input: I) -> Result<f32, String>
+where
+    I: AsRef<Path>,
+{
+    let output = Command::new("ffprobe")
+        .args(["-v", "error"])
+        .args(["-show_entries", "format=duration"])
+        .args(["-of", "default=noprint_wrappers=1:nokey=1"])
+        .arg(input.as_ref())
+        .output()
+        .map_err(|err| format!("Failed to execute ffprobe: {}", err))?;
+    if !output.status.success() {
+        return Err(format!("ffprobe exited with {:?}", output.status.code()));
+    }
+    parse_ffprobe_duration(&output.stdout)
+}
+
+/// Parses the stdout of an `ffprobe -show_entries format=duration
+/// -of default=noprint_wrappers=1:nokey=1` invocation.
+fn parse_ffprobe_duration(stdout: &[u8]) -> Result<f32, String> {
+    String::from_utf8_lossy(stdout)
+        .trim()
+        .parse()
+        .map_err(|err| format!("Failed to parse ffprobe duration: {}", err))
+}
+
+/// Runs `ffprobe` against `input` to gather the facts in [`ProbeInfo`],
+/// failing fast if the file cannot be probed.
+fn probe<I>(input: I) -> Result<ProbeInfo, String>
+where
+    I: AsRef<Path>,
+{
+    let duration = ffprobe_duration(input.as_ref())?;
+
+    let output = Command::new("ffprobe")
+        .args(["-v", "error"])
+        .args(["-select_streams", "a"])
+        .args(["-show_entries", "stream=index"])
+        .args(["-of", "default=noprint_wrappers=1:nokey=1"])
+        .arg(input.as_ref())
+        .output()
+        .map_err(|err| format!("Failed to execute ffprobe: {}", err))?;
+    if !output.status.success() {
+        return Err(format!("ffprobe exited with {:?}", output.status.code()));
+    }
+    let has_audio = parse_has_audio_stream(&output.stdout);
+
+    Ok(ProbeInfo {
+        duration,
+        has_audio,
+    })
+}
+
+/// Parses the stdout of an `ffprobe -select_streams a -show_entries
+/// stream=index` invocation: any output means at least one audio stream.
+fn parse_has_audio_stream(stdout: &[u8]) -> bool {
+    !String::from_utf8_lossy(stdout).trim().is_empty()
+}
+
+/// Runs `work` over `items` across a pool of `workers` threads, returning
+/// every error `work` produced. Each item is claimed by exactly one worker
+/// via a shared cursor, so completion order is unspecified, but the caller
+/// is free to rely on `items` itself keeping its original order throughout.
+fn run_pooled<T, F>(items: &[T], workers: usize, work: F) -> Vec<String>
+where
+    T: Sync,
+    F: Fn(&T) -> Result<(), String> + Sync,
+{
+    let next = AtomicUsize::new(0);
+    let errors = Mutex::new(Vec::new());
+    thread::scope(|scope| {
+        for _ in 0..workers.max(1) {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(item) = items.get(index) else {
+                    break;
+                };
+                if let Err(err) = work(item) {
+                    errors.lock().unwrap().push(err);
+                }
+            });
+        }
+    });
+    errors.into_inner().unwrap()
+}
+
+/// Builds an `atempo` filter chain equivalent to a single `factor`x speed-up,
+/// since `atempo` only accepts factors in `0.5..=2.0` per stage.
+fn atempo_chain(mut factor: f32) -> String {
+    let mut stages = Vec::new();
+    while factor > 2.0 {
+        stages.push(2.0);
+        factor /= 2.0;
+    }
+    while factor < 0.5 {
+        stages.push(0.5);
+        factor /= 0.5;
+    }
+    stages.push(factor);
+    stages
+        .into_iter()
+        .map(|stage| format!("atempo={stage}"))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Extracts the `[timestamp, timestamp + duration)` slice of `input` into
+/// `output`, re-encoding it. If `speed` is set, also applies it as a
+/// `setpts`/`atempo` speed-up. Returns an error
+/// message (rather than exiting the process directly) so that callers
+/// running several slices concurrently can aggregate failures across
+/// workers.
+fn slice<I, O>(
+    timestamp: f32,
+    duration: f32,
+    input: I,
+    output: O,
+    speed: Option<f32>,
+) -> Result<(), String>
 where
     I: AsRef<Path>,
     O: AsRef<Path>,
 {
-    let status = Command::new("ffmpeg")
+    let mut command = Command::new("ffmpeg");
+    command
         .args(["-ss", &timestamp.to_string()])
         .args(["-t", &duration.to_string()])
         .arg("-i")
-        .arg(input.as_ref())
+        .arg(input.as_ref());
+    if let Some(speed) = speed {
+        command
+            .args(["-vf", &format!("setpts=PTS/{speed}")])
+            .args(["-af", &atempo_chain(speed)]);
+    }
+    let status = command
         .arg(output.as_ref())
         .status()
-        .unwrap_or_else(|err| {
-            error!("Failed to extract sub-video: {}", err);
-            exit(err.raw_os_error().unwrap_or(1))
-        });
+        .map_err(|err| format!("Failed to extract sub-video: {}", err))?;
     if !status.success() {
-        error!("Failed to extract a piece");
-        exit(status.code().unwrap_or(1))
+        return Err(format!(
+            "Failed to extract {:?}: ffmpeg exited with {:?}",
+            output.as_ref(),
+            status.code()
+        ));
     }
+    Ok(())
 }
 
-fn concatenate<I, O>(input: I, output: O)
+/// A `filter_complex` graph chaining `xfade`/`acrossfade` transitions across
+/// a run of input pieces, plus the final output pad labels to `-map`.
+struct XfadeGraph {
+    filter_complex: String,
+    video_label: String,
+    audio_label: String,
+}
+
+impl XfadeGraph {
+    /// Builds the graph for pieces of the given `durations`, cross-faded by
+    /// `transition` seconds each. `offset` for the Nth transition is the
+    /// timestamp, within the stream merged so far, where that transition
+    /// should start: the merged duration so far minus one transition length.
+    fn build(durations: &[f32], transition: f32) -> Self {
+        let mut video_filters = Vec::new();
+        let mut audio_filters = Vec::new();
+        let mut prev_v = "0:v".to_string();
+        let mut prev_a = "0:a".to_string();
+        let mut merged_duration = durations[0];
+        for (i, duration) in durations.iter().enumerate().skip(1) {
+            let offset = merged_duration - transition;
+            let next_v = format!("v{i}");
+            let next_a = format!("a{i}");
+            video_filters.push(format!(
+                "[{prev_v}][{i}:v]xfade=transition=fade:duration={transition}:offset={offset}[{next_v}]"
+            ));
+            audio_filters.push(format!("[{prev_a}][{i}:a]acrossfade=d={transition}[{next_a}]"));
+            prev_v = next_v;
+            prev_a = next_a;
+            merged_duration += duration - transition;
+        }
+        let filter_complex = video_filters
+            .into_iter()
+            .chain(audio_filters)
+            .collect::<Vec<_>>()
+            .join(";");
+        XfadeGraph {
+            filter_complex,
+            video_label: prev_v,
+            audio_label: prev_a,
+        }
+    }
+}
+
+/// Joins `pieces` (in order) into `output` by chaining `xfade` (video) and
+/// `acrossfade` (audio) transitions of `transition` seconds between each
+/// consecutive pair, instead of a hard cut. Requires re-encoding, so it is
+/// slower than the plain concat paths in [`concatenate`].
+fn concatenate_xfade<P, O>(pieces: &[P], transition: f32, output: O) -> Result<(), String>
+where
+    P: AsRef<Path>,
+    O: AsRef<Path>,
+{
+    if pieces.len() < 2 {
+        return Err("need at least two pieces to build transitions".to_string());
+    }
+
+    let durations = pieces
+        .iter()
+        .map(|piece| ffprobe_duration(piece.as_ref()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let xfade = XfadeGraph::build(&durations, transition);
+
+    let mut command = Command::new("ffmpeg");
+    for piece in pieces {
+        command.arg("-i").arg(piece.as_ref());
+    }
+    command
+        .arg("-filter_complex")
+        .arg(xfade.filter_complex)
+        .arg("-map")
+        .arg(format!("[{}]", xfade.video_label))
+        .arg("-map")
+        .arg(format!("[{}]", xfade.audio_label))
+        .arg(output.as_ref());
+    let status = command
+        .status()
+        .map_err(|err| format!("Failed to execute ffmpeg: {}", err))?;
+    if !status.success() {
+        return Err(format!("ffmpeg exited with {:?}", status.code()));
+    }
+    Ok(())
+}
+
+/// Joins the pieces listed in `concat_script` (in chronological order, one
+/// `file` directive per line) into `output`, using the backend selected by
+/// `method`.
+fn concatenate<S, P, O>(method: ConcatMethod, concat_script: S, pieces: &[P], output: O)
+where
+    S: AsRef<Path>,
+    P: AsRef<Path>,
+    O: AsRef<Path>,
+{
+    match method {
+        ConcatMethod::Ffmpeg => concatenate_ffmpeg(concat_script, output),
+        ConcatMethod::Mkvmerge => concatenate_mkvmerge(pieces, output),
+    }
+}
+
+fn concatenate_ffmpeg<I, O>(input: I, output: O)
 where
     I: AsRef<Path>,
     O: AsRef<Path>,
@@ -145,3 +596,170 @@ where
         exit(status.code().unwrap_or(1))
     }
 }
+
+/// Builds the `mkvmerge -o <out> <first> + <second> + ...` argument list.
+fn mkvmerge_args<P, O>(pieces: &[P], output: O) -> Vec<OsString>
+where
+    P: AsRef<Path>,
+    O: AsRef<Path>,
+{
+    let mut args = vec![OsString::from("-o"), output.as_ref().as_os_str().to_owned()];
+    for (index, piece) in pieces.iter().enumerate() {
+        if index > 0 {
+            args.push(OsString::from("+"));
+        }
+        args.push(piece.as_ref().as_os_str().to_owned());
+    }
+    args
+}
+
+fn concatenate_mkvmerge<P, O>(pieces: &[P], output: O)
+where
+    P: AsRef<Path>,
+    O: AsRef<Path>,
+{
+    let status = Command::new("mkvmerge")
+        .args(mkvmerge_args(pieces, output))
+        .status()
+        .unwrap_or_else(|err| {
+            error!("Failed to execute mkvmerge: {}", err);
+            exit(err.raw_os_error().unwrap_or(1))
+        });
+    if !status.success() {
+        error!("Failed to concatenate pieces");
+        exit(status.code().unwrap_or(1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mkvmerge_args_joins_pieces_with_plus() {
+        let pieces = ["piece-0.mkv", "piece-1.mkv", "piece-2.mkv"];
+        let args = mkvmerge_args(&pieces, "out.mkv");
+        assert_eq!(
+            args,
+            vec![
+                OsString::from("-o"),
+                OsString::from("out.mkv"),
+                OsString::from("piece-0.mkv"),
+                OsString::from("+"),
+                OsString::from("piece-1.mkv"),
+                OsString::from("+"),
+                OsString::from("piece-2.mkv"),
+            ]
+        );
+    }
+
+    #[test]
+    fn mkvmerge_args_single_piece_has_no_plus() {
+        let pieces = ["piece-0.mkv"];
+        let args = mkvmerge_args(&pieces, "out.mkv");
+        assert_eq!(
+            args,
+            vec![
+                OsString::from("-o"),
+                OsString::from("out.mkv"),
+                OsString::from("piece-0.mkv"),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_ffprobe_duration_parses_trimmed_number() {
+        assert_eq!(parse_ffprobe_duration(b"12.345000\n").unwrap(), 12.345);
+    }
+
+    #[test]
+    fn parse_ffprobe_duration_rejects_garbage() {
+        assert!(parse_ffprobe_duration(b"N/A\n").is_err());
+    }
+
+    #[test]
+    fn parse_has_audio_stream_detects_presence() {
+        assert!(parse_has_audio_stream(b"0\n"));
+        assert!(!parse_has_audio_stream(b"\n"));
+        assert!(!parse_has_audio_stream(b""));
+    }
+
+    #[test]
+    fn run_pooled_processes_every_item_exactly_once() {
+        let items: Vec<usize> = (0..20).collect();
+        let seen = Mutex::new(Vec::new());
+        let errors = run_pooled(&items, 4, |item| {
+            seen.lock().unwrap().push(*item);
+            Ok(())
+        });
+        assert!(errors.is_empty());
+        let mut seen = seen.into_inner().unwrap();
+        seen.sort_unstable();
+        assert_eq!(seen, items);
+    }
+
+    #[test]
+    fn run_pooled_aggregates_every_error() {
+        let items: Vec<usize> = (0..10).collect();
+        let errors = run_pooled(&items, 4, |item| {
+            if item % 2 == 0 {
+                Err(format!("boom {item}"))
+            } else {
+                Ok(())
+            }
+        });
+        assert_eq!(errors.len(), 5);
+    }
+
+    #[test]
+    fn atempo_chain_single_stage() {
+        assert_eq!(atempo_chain(1.0), "atempo=1");
+        assert_eq!(atempo_chain(2.0), "atempo=2");
+        assert_eq!(atempo_chain(0.5), "atempo=0.5");
+    }
+
+    #[test]
+    fn atempo_chain_multiple_stages() {
+        assert_eq!(atempo_chain(4.0), "atempo=2,atempo=2");
+        assert_eq!(atempo_chain(0.125), "atempo=0.5,atempo=0.5,atempo=0.5");
+    }
+
+    #[test]
+    fn parse_speed_factor_accepts_positive_numbers() {
+        assert_eq!(parse_speed_factor("4").unwrap(), 4.0);
+        assert_eq!(parse_speed_factor("0.5").unwrap(), 0.5);
+    }
+
+    #[test]
+    fn parse_speed_factor_rejects_non_positive_and_invalid() {
+        assert!(parse_speed_factor("0").is_err());
+        assert!(parse_speed_factor("-1").is_err());
+        assert!(parse_speed_factor("not-a-number").is_err());
+    }
+
+    #[test]
+    fn xfade_graph_build_two_pieces_offsets_by_transition() {
+        let xfade = XfadeGraph::build(&[10.0, 5.0], 1.0);
+        assert_eq!(
+            xfade.filter_complex,
+            "[0:v][1:v]xfade=transition=fade:duration=1:offset=9[v1];\
+             [0:a][1:a]acrossfade=d=1[a1]"
+        );
+        assert_eq!(xfade.video_label, "v1");
+        assert_eq!(xfade.audio_label, "a1");
+    }
+
+    #[test]
+    fn xfade_graph_build_chains_offsets_across_pieces() {
+        let xfade = XfadeGraph::build(&[10.0, 5.0, 8.0], 2.0);
+        assert_eq!(
+            xfade.filter_complex,
+            "[0:v][1:v]xfade=transition=fade:duration=2:offset=8[v1];\
+             [v1][2:v]xfade=transition=fade:duration=2:offset=11[v2];\
+             [0:a][1:a]acrossfade=d=2[a1];\
+             [a1][2:a]acrossfade=d=2[a2]"
+        );
+        assert_eq!(xfade.video_label, "v2");
+        assert_eq!(xfade.audio_label, "a2");
+    }
+}